@@ -0,0 +1,131 @@
+use crate::common::BitSet;
+use crate::directory::ReadOnlySource;
+use crate::DocId;
+use std::io;
+use std::io::Write;
+
+/// Set of the alive documents of a segment.
+///
+/// A set bit at position `doc` means the document is **alive** (i.e. it has not
+/// been deleted). This is the inverse of the historical deleted-doc `BitSet`
+/// convention.
+///
+/// Unlike a [`BitSet`], an `AliveBitSet` is backed by a [`ReadOnlySource`], so
+/// it can be serialized densely to the `.del` segment component and then
+/// memory-mapped straight back on segment open. A large segment carrying only a
+/// handful of deletes therefore no longer pays for a full heap bitset
+/// allocation.
+#[derive(Clone)]
+pub struct AliveBitSet {
+    data: ReadOnlySource,
+    num_alive_docs: usize,
+}
+
+impl AliveBitSet {
+    /// Opens an `AliveBitSet` from the raw bytes of a `.del` component.
+    ///
+    /// The number of alive documents is counted eagerly once; testing
+    /// individual documents afterwards is a cheap, allocation-free bit lookup.
+    pub fn open(data: ReadOnlySource) -> AliveBitSet {
+        let num_alive_docs = data
+            .as_slice()
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum();
+        AliveBitSet {
+            data,
+            num_alive_docs,
+        }
+    }
+
+    /// Serializes the alive documents as a dense bitset.
+    ///
+    /// Every `DocId` in `0..max_doc` that is **not** present in `deleted_docs`
+    /// is written as alive.
+    pub fn serialize<W: Write>(
+        max_doc: DocId,
+        deleted_docs: &BitSet,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        let num_bytes = ((max_doc as usize) + 7) / 8;
+        let mut buffer = vec![0u8; num_bytes];
+        for doc in 0..max_doc {
+            if !deleted_docs.contains(doc) {
+                buffer[(doc / 8) as usize] |= 1u8 << (doc % 8);
+            }
+        }
+        writer.write_all(&buffer)
+    }
+
+    /// Returns true if and only if the document is alive.
+    ///
+    /// `doc` must be a valid `DocId` of the segment (`doc < max_doc`). A
+    /// `DocId` past the end of the bitset is reported as not alive rather than
+    /// panicking.
+    #[inline]
+    pub fn is_alive(&self, doc: DocId) -> bool {
+        match self.data.as_slice().get((doc / 8) as usize) {
+            Some(&byte) => byte & (1u8 << (doc % 8)) != 0,
+            None => false,
+        }
+    }
+
+    /// Returns true if and only if the document is deleted.
+    #[inline]
+    pub fn is_deleted(&self, doc: DocId) -> bool {
+        !self.is_alive(doc)
+    }
+
+    /// Number of alive documents in the segment.
+    pub fn num_alive_docs(&self) -> usize {
+        self.num_alive_docs
+    }
+
+    /// Raw bytes backing the alive bitset, in the dense `.del` layout.
+    ///
+    /// This is what gets copied into the `.del` component when a volatile
+    /// segment is persisted, so the in-memory deletes are not round-tripped
+    /// through a heap `BitSet`.
+    pub fn as_slice(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+
+    /// Iterates over every alive `DocId` in increasing order.
+    pub fn alive_docs(&self) -> impl Iterator<Item = DocId> + '_ {
+        let max_doc = (self.data.len() * 8) as DocId;
+        (0..max_doc).filter(move |&doc| self.is_alive(doc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::BitSet;
+
+    #[test]
+    fn serialize_open_round_trip_with_non_multiple_of_8_doc_count() {
+        let max_doc = 11;
+        let mut deleted_docs = BitSet::with_max_value(max_doc);
+        deleted_docs.insert(2);
+        deleted_docs.insert(7);
+        deleted_docs.insert(10);
+
+        let mut buffer = Vec::new();
+        AliveBitSet::serialize(max_doc, &deleted_docs, &mut buffer).unwrap();
+        let alive_bitset = AliveBitSet::open(ReadOnlySource::from(buffer));
+
+        for doc in 0..max_doc {
+            assert_eq!(
+                alive_bitset.is_alive(doc),
+                !deleted_docs.contains(doc),
+                "doc {} alive mismatch",
+                doc
+            );
+        }
+        assert_eq!(alive_bitset.num_alive_docs(), (max_doc - 3) as usize);
+        assert_eq!(
+            alive_bitset.alive_docs().collect::<Vec<DocId>>(),
+            vec![0, 1, 3, 4, 5, 6, 8, 9]
+        );
+    }
+}
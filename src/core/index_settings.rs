@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// Sort order of an index sort key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Order {
+    /// Ascending order: smallest fast field values first.
+    Asc,
+    /// Descending order: largest fast field values first.
+    Desc,
+}
+
+impl Order {
+    /// Returns true for a descending order.
+    pub fn is_desc(&self) -> bool {
+        *self == Order::Desc
+    }
+
+    /// Returns true for an ascending order.
+    pub fn is_asc(&self) -> bool {
+        *self == Order::Asc
+    }
+}
+
+/// Describes the field the index is sorted by, together with the order.
+///
+/// The field must refer to an existing fast field.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexSortByField {
+    /// Name of the fast field to sort by.
+    pub field: String,
+    /// Ascending or descending.
+    pub order: Order,
+}
+
+/// Settings applying to the whole index.
+///
+/// The `sort_by_field` setting is meant to make every segment store its
+/// documents in the requested order, so that range queries and top-K-by-field
+/// collectors can early-terminate. The permutation machinery that would
+/// enforce this --
+/// [`compute_doc_id_mapping`](crate::indexer::doc_id_mapping::compute_doc_id_mapping),
+/// [`remap_alive_bitset`](crate::indexer::doc_id_mapping::remap_alive_bitset)
+/// and [`k_way_merge`](crate::indexer::doc_id_mapping::k_way_merge) -- exists,
+/// but nothing in the crate wires it into `SegmentMeta`, a concrete
+/// `SerializableSegment::write`, or the merge path yet: setting
+/// `sort_by_field` today has no effect on how a segment is actually written,
+/// merged, or persisted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexSettings {
+    /// Optional sort key applied during serialization and merge.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_by_field: Option<IndexSortByField>,
+}
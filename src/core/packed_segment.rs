@@ -0,0 +1,270 @@
+use crate::core::SegmentComponent;
+use crate::core::SegmentMeta;
+use crate::directory::ReadOnlySource;
+use crate::directory::WritePtr;
+use crate::directory::TerminatingWrite;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Number of bytes used to encode the footer length at the tail of a packed
+/// segment file.
+const FOOTER_LEN_BYTES: usize = 8;
+
+/// On-disk format version of [`PackedSegmentFooter`].
+///
+/// Bumped whenever the footer's binary layout or the meaning of
+/// [`component_tag`] changes, so a reader refuses a file it would otherwise
+/// silently misinterpret instead of resolving a component to the wrong
+/// region.
+const FOOTER_FORMAT_VERSION: u32 = 1;
+
+/// Relative path of the single physical file backing a packed segment.
+pub(crate) fn packed_relative_path(meta: &SegmentMeta) -> PathBuf {
+    PathBuf::from(format!("{}.pack", meta.id().uuid_string()))
+}
+
+/// Stable tag identifying a `SegmentComponent` in the packed footer.
+///
+/// These values are fixed by this match, not derived from
+/// `SegmentComponent::iterator()`'s position: the iterator's order is free to
+/// change (a reordering or a new variant is a routine refactor elsewhere in
+/// the crate), and doing so must not silently remap an existing `.pack`
+/// file's regions to the wrong component. The on-disk format does not depend
+/// on `SegmentComponent` deriving `Serialize`/`Deserialize`; only
+/// `Copy`/`PartialEq` (already required elsewhere) are used here.
+fn component_tag(component: SegmentComponent) -> u32 {
+    match component {
+        SegmentComponent::Postings => 0,
+        SegmentComponent::Positions => 1,
+        SegmentComponent::FastFields => 2,
+        SegmentComponent::FieldNorms => 3,
+        SegmentComponent::Terms => 4,
+        SegmentComponent::Store => 5,
+        SegmentComponent::TempStore => 6,
+        SegmentComponent::Delete => 7,
+    }
+}
+
+/// One entry of the packed footer: a component (by stable tag) and the
+/// `(offset, len)` region it occupies in the physical file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ComponentRegion {
+    component_tag: u32,
+    offset: u64,
+    len: u64,
+}
+
+/// Offset table mapping each `SegmentComponent` to its `(offset, len)` region
+/// inside a packed segment file.
+///
+/// It is stored as a small JSON footer at the end of the physical file, much
+/// like an object-addressed segment store. A `Vec` of entries is used rather
+/// than a map keyed by `SegmentComponent`, since `serde_json` only accepts
+/// string-valued map keys.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PackedSegmentFooter {
+    version: u32,
+    regions: Vec<ComponentRegion>,
+}
+
+impl Default for PackedSegmentFooter {
+    fn default() -> PackedSegmentFooter {
+        PackedSegmentFooter {
+            version: FOOTER_FORMAT_VERSION,
+            regions: Vec::new(),
+        }
+    }
+}
+
+impl PackedSegmentFooter {
+    fn region(&self, component: SegmentComponent) -> Option<(u64, u64)> {
+        let tag = component_tag(component);
+        self.regions
+            .iter()
+            .find(|region| region.component_tag == tag)
+            .map(|region| (region.offset, region.len))
+    }
+}
+
+/// Writes every component blob of a segment into a single physical file,
+/// finalizing the offset table footer on [`close`](PackedSegmentWriter::close).
+///
+/// Components are simply appended in the order they are written; the footer
+/// records where each one lands.
+pub struct PackedSegmentWriter {
+    write: WritePtr,
+    footer: PackedSegmentFooter,
+    offset: u64,
+}
+
+impl PackedSegmentWriter {
+    /// Wraps a freshly opened packed-file writer.
+    pub fn new(write: WritePtr) -> PackedSegmentWriter {
+        PackedSegmentWriter {
+            write,
+            footer: PackedSegmentFooter::default(),
+            offset: 0,
+        }
+    }
+
+    /// Appends a component blob and records its region in the footer.
+    pub fn append(&mut self, component: SegmentComponent, blob: &[u8]) -> io::Result<()> {
+        self.write.write_all(blob)?;
+        self.footer.regions.push(ComponentRegion {
+            component_tag: component_tag(component),
+            offset: self.offset,
+            len: blob.len() as u64,
+        });
+        self.offset += blob.len() as u64;
+        Ok(())
+    }
+
+    /// Serializes the offset table and fsyncs the packed file.
+    ///
+    /// The layout is: all component blobs, the JSON-encoded footer, then the
+    /// footer length as a little-endian `u64` so a reader can locate the table
+    /// from the tail.
+    pub fn close(mut self) -> io::Result<()> {
+        let footer_bytes = serde_json::to_vec(&self.footer)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        self.write.write_all(&footer_bytes)?;
+        self.write
+            .write_all(&(footer_bytes.len() as u64).to_le_bytes())?;
+        self.write.terminate()?;
+        Ok(())
+    }
+}
+
+/// Zero-copy reader over a packed segment file.
+///
+/// The whole file is held as a single `ReadOnlySource` (typically mmapped);
+/// [`open_read`](PackedSegmentReader::open_read) slices a component's sub-range
+/// out of it without opening a distinct path.
+pub struct PackedSegmentReader {
+    source: ReadOnlySource,
+    footer: PackedSegmentFooter,
+}
+
+impl PackedSegmentReader {
+    /// Parses the footer of a packed segment file.
+    ///
+    /// `source` must be the packed payload only -- the trailing 8 bytes are
+    /// expected to be the packed footer length written by
+    /// [`PackedSegmentWriter::close`], not tantivy's managed footer.
+    /// `ManagedDirectory::open_read` strips the managed footer (the bytes
+    /// appended by `TerminatingWrite::terminate` through `open_write`) before
+    /// returning the source, so the length word sits at the tail as expected;
+    /// passing a raw, un-stripped source here would misread the managed footer
+    /// as `footer_len`.
+    pub fn open(source: ReadOnlySource) -> io::Result<PackedSegmentReader> {
+        let data = source.as_slice();
+        if data.len() < FOOTER_LEN_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "packed segment file is too small to contain a footer",
+            ));
+        }
+        let len_start = data.len() - FOOTER_LEN_BYTES;
+        let mut len_bytes = [0u8; FOOTER_LEN_BYTES];
+        len_bytes.copy_from_slice(&data[len_start..]);
+        let footer_len = u64::from_le_bytes(len_bytes) as usize;
+        let footer_start = len_start.checked_sub(footer_len).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid packed segment footer length",
+            )
+        })?;
+        let footer: PackedSegmentFooter = serde_json::from_slice(&data[footer_start..len_start])
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        if footer.version != FOOTER_FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "unsupported packed segment footer version {} (expected {})",
+                    footer.version, FOOTER_FORMAT_VERSION
+                ),
+            ));
+        }
+        for region in &footer.regions {
+            let region_end = region.offset.checked_add(region.len).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "packed segment footer region overflows (offset + len)",
+                )
+            })?;
+            if region_end > data.len() as u64 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "packed segment footer region is out of bounds of the file",
+                ));
+            }
+        }
+        Ok(PackedSegmentReader { source, footer })
+    }
+
+    /// Slices the sub-range of `component` out of the packed file.
+    ///
+    /// Returns `None` if the component was not stored in this packed file.
+    pub fn open_read(&self, component: SegmentComponent) -> Option<ReadOnlySource> {
+        let (offset, len) = self.footer.region(component)?;
+        Some(self.source.slice(offset as usize, (offset + len) as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::directory::{Directory, RAMDirectory};
+    use std::path::Path;
+
+    #[test]
+    fn writer_reader_round_trip_with_multiple_components() {
+        let mut ram_directory = RAMDirectory::create();
+        let path = Path::new("test.pack");
+        let mut writer = PackedSegmentWriter::new(ram_directory.open_write(path).unwrap());
+        writer
+            .append(SegmentComponent::Store, b"store-bytes")
+            .unwrap();
+        writer
+            .append(SegmentComponent::Postings, b"postings-bytes-longer")
+            .unwrap();
+        writer.close().unwrap();
+
+        let source = ram_directory.open_read(path).unwrap();
+        let reader = PackedSegmentReader::open(source).unwrap();
+        assert_eq!(
+            reader.open_read(SegmentComponent::Store).unwrap().as_slice(),
+            b"store-bytes"
+        );
+        assert_eq!(
+            reader
+                .open_read(SegmentComponent::Postings)
+                .unwrap()
+                .as_slice(),
+            b"postings-bytes-longer"
+        );
+        assert!(reader.open_read(SegmentComponent::Terms).is_none());
+    }
+
+    #[test]
+    fn open_rejects_a_footer_region_out_of_bounds_instead_of_panicking() {
+        let footer = PackedSegmentFooter {
+            version: FOOTER_FORMAT_VERSION,
+            regions: vec![ComponentRegion {
+                component_tag: component_tag(SegmentComponent::Store),
+                offset: 0,
+                // There is no payload at all in `data` below, so this region
+                // reaches past the end of the file.
+                len: 4096,
+            }],
+        };
+        let footer_bytes = serde_json::to_vec(&footer).unwrap();
+        let mut data = footer_bytes.clone();
+        data.extend_from_slice(&(footer_bytes.len() as u64).to_le_bytes());
+
+        let err = PackedSegmentReader::open(ReadOnlySource::from(data)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,5 +1,9 @@
 use super::SegmentComponent;
+use crate::common::AliveBitSet;
+use crate::core::packed_segment::{packed_relative_path, PackedSegmentReader};
 use crate::core::Index;
+use crate::core::IndexSettings;
+use crate::core::IndexSortByField;
 use crate::core::SegmentId;
 use crate::core::SegmentMeta;
 use crate::directory::error::{OpenReadError, OpenWriteError};
@@ -27,6 +31,22 @@ impl From<ManagedDirectory> for SegmentDirectory {
     }
 }
 
+impl SegmentDirectory {
+    /// Whether `Segment::open_read` looks for a bundled single-file
+    /// ("packed") layout in this directory.
+    ///
+    /// Volatile RAM segments are always multi-file. In a persisted directory,
+    /// a segment is read from its `.pack` file when one exists (as written by
+    /// `SegmentEntry::persist`); otherwise its per-component files are read.
+    /// `Segment::open_write` always writes per-component files.
+    pub(crate) fn is_packed(&self) -> bool {
+        match self {
+            SegmentDirectory::Volatile(_) => false,
+            SegmentDirectory::Persisted(_) => true,
+        }
+    }
+}
+
 impl Deref for SegmentDirectory {
     type Target = Directory;
 
@@ -52,6 +72,7 @@ impl DerefMut for SegmentDirectory {
 pub struct Segment {
     schema: Schema,
     meta: SegmentMeta,
+    index_settings: IndexSettings,
     directory: SegmentDirectory,
 }
 
@@ -73,6 +94,7 @@ impl Segment {
         Segment {
             directory: SegmentDirectory::Persisted(index.directory().clone()),
             schema: index.schema(),
+            index_settings: index.settings().clone(),
             meta,
         }
     }
@@ -81,10 +103,15 @@ impl Segment {
     ///
     /// That segment is entirely dissociated from the index directory.
     /// It will be persisted by a background thread in charge of IO.
-    pub fn new_unpersisted(meta: SegmentMeta, schema: Schema) -> Segment {
+    pub fn new_unpersisted(
+        meta: SegmentMeta,
+        schema: Schema,
+        index_settings: IndexSettings,
+    ) -> Segment {
         Segment {
             schema,
             meta,
+            index_settings,
             directory: SegmentDirectory::Volatile(RAMDirectory::create()),
         }
     }
@@ -94,6 +121,18 @@ impl Segment {
         &self.meta
     }
 
+    /// Returns the configured index sort order, if any.
+    ///
+    /// A sorted index is meant to store the documents of this segment in the
+    /// order of the returned fast field, so range queries and top-K-by-field
+    /// collectors can early-terminate. No concrete `SerializableSegment::write`
+    /// or merge implementation in this crate honors this setting yet -- see
+    /// [`IndexSettings`] -- so today this accessor just round-trips whatever
+    /// was configured without the segment actually being laid out that way.
+    pub fn sort_by_field(&self) -> Option<IndexSortByField> {
+        self.index_settings.sort_by_field.clone()
+    }
+
     pub(crate) fn directory(&self) -> &SegmentDirectory {
         &self.directory
     }
@@ -106,6 +145,7 @@ impl Segment {
         Segment {
             directory: self.directory,
             schema: self.schema,
+            index_settings: self.index_settings,
             meta: self.meta.with_max_doc(max_doc),
         }
     }
@@ -115,6 +155,7 @@ impl Segment {
         Segment {
             directory: self.directory,
             schema: self.schema,
+            index_settings: self.index_settings,
             meta: self.meta.with_delete_meta(num_deleted_docs, opstamp),
         }
     }
@@ -132,16 +173,53 @@ impl Segment {
         self.meta.relative_path(component)
     }
 
+    /// Relative path of the single physical file backing a packed segment.
+    ///
+    /// All component blobs of the segment live in this one file; GC in
+    /// `ManagedDirectory` tracks it as a single unit.
+    pub fn packed_path(&self) -> PathBuf {
+        packed_relative_path(&self.meta)
+    }
+
     /// Open one of the component file for a *regular* read.
+    ///
+    /// For a packed (single-file) segment the component's sub-range is sliced,
+    /// zero-copy, out of the bundled file; otherwise the component's own file
+    /// is opened.
     pub fn open_read(
         &self,
         component: SegmentComponent,
     ) -> result::Result<ReadOnlySource, OpenReadError> {
+        if self.directory.is_packed() {
+            let packed_path = self.packed_path();
+            if self.directory.exists(&packed_path) {
+                let packed_source = self.directory.open_read(&packed_path)?;
+                let reader = PackedSegmentReader::open(packed_source)
+                    .map_err(|io_error| OpenReadError::IoError(io_error.into()))?;
+                return reader.open_read(component).ok_or_else(|| {
+                    OpenReadError::FileDoesNotExist(self.relative_path(component))
+                });
+            }
+        }
         let path = self.relative_path(component);
         let source = self.directory.open_read(&path)?;
         Ok(source)
     }
 
+    /// Opens the segment's alive-doc bitset, lazily memory-mapping the
+    /// `.del` component.
+    ///
+    /// Returns `None` when the segment has no `.del` component, in which case
+    /// every document is alive. The returned `AliveBitSet` is backed directly
+    /// by the mmapped source, so no full bitset is deserialized onto the heap.
+    pub fn open_alive_bitset(&self) -> result::Result<Option<AliveBitSet>, OpenReadError> {
+        if self.meta.num_deleted_docs() == 0 {
+            return Ok(None);
+        }
+        let source = self.open_read(SegmentComponent::Delete)?;
+        Ok(Some(AliveBitSet::open(source)))
+    }
+
     /// Open one of the component file for *regular* write.
     pub fn open_write(
         &mut self,
@@ -157,6 +235,20 @@ pub trait SerializableSegment {
     /// Writes a view of a segment by pushing information
     /// to the `SegmentSerializer`.
     ///
+    /// A sorted index (see [`Segment::sort_by_field`]) is meant to have its
+    /// implementation compute a
+    /// [`DocIdMapping`](crate::indexer::doc_id_mapping::DocIdMapping) via
+    /// [`compute_doc_id_mapping`](crate::indexer::doc_id_mapping::compute_doc_id_mapping)
+    /// and emit the doc store, postings, positions, fast fields and alive
+    /// bitset by walking its old doc ids in the new order, so every component
+    /// stays internally consistent; at merge time the per-segment sorted
+    /// streams would be combined with
+    /// [`k_way_merge`](crate::indexer::doc_id_mapping::k_way_merge). No
+    /// implementation of this trait in the crate does so yet -- the doc-id
+    /// mapping and merge machinery above is unwired scaffolding, exercised
+    /// only by its own unit tests, and `sort_by_field` has no effect on how a
+    /// segment is actually written or merged today.
+    ///
     /// # Returns
     /// The number of documents in the segment.
     fn write(&self, serializer: SegmentSerializer) -> Result<u32>;
@@ -0,0 +1,264 @@
+use crate::core::{IndexSortByField, Order};
+use crate::DocId;
+
+/// Permutation mapping the sorted `new_doc_id` space to the original
+/// `old_doc_id` space of a segment (or a set of merged segments).
+///
+/// Walking [`iter_old_doc_ids`] yields the documents in the order given by an
+/// [`IndexSortByField`]. The `SegmentSerializer` and the merge path do not use
+/// this mapping: segments are written in their original doc-id order.
+///
+/// [`iter_old_doc_ids`]: DocIdMapping::iter_old_doc_ids
+pub(crate) struct DocIdMapping {
+    new_doc_id_to_old: Vec<DocId>,
+    old_doc_id_to_new: Vec<Option<DocId>>,
+}
+
+impl DocIdMapping {
+    /// Builds a mapping from the `new -> old` permutation.
+    ///
+    /// `old_max_doc` is the number of documents in the original space; deleted
+    /// documents are simply absent from `new_doc_id_to_old`.
+    fn from_new_to_old(new_doc_id_to_old: Vec<DocId>, old_max_doc: DocId) -> DocIdMapping {
+        let mut old_doc_id_to_new = vec![None; old_max_doc as usize];
+        for (new_doc_id, &old_doc_id) in new_doc_id_to_old.iter().enumerate() {
+            old_doc_id_to_new[old_doc_id as usize] = Some(new_doc_id as DocId);
+        }
+        DocIdMapping {
+            new_doc_id_to_old,
+            old_doc_id_to_new,
+        }
+    }
+
+    /// Iterates over the old `DocId`s in the new (sorted) order.
+    pub(crate) fn iter_old_doc_ids(&self) -> impl Iterator<Item = DocId> + '_ {
+        self.new_doc_id_to_old.iter().copied()
+    }
+
+    /// Number of documents in the remapped (alive) space.
+    pub(crate) fn num_docs(&self) -> DocId {
+        self.new_doc_id_to_old.len() as DocId
+    }
+
+    /// Translates an old `DocId` into its new one, or `None` if it was deleted.
+    pub(crate) fn get_new_doc_id(&self, old_doc_id: DocId) -> Option<DocId> {
+        self.old_doc_id_to_new[old_doc_id as usize]
+    }
+
+    /// Translates a new `DocId` back into the original one.
+    pub(crate) fn get_old_doc_id(&self, new_doc_id: DocId) -> DocId {
+        self.new_doc_id_to_old[new_doc_id as usize]
+    }
+}
+
+/// Computes the `new_doc_id -> old_doc_id` permutation for a single segment by
+/// sorting its alive documents on the configured fast field.
+///
+/// `value_for` yields the sort field's fast value for an old `DocId`; `alive`
+/// reports whether a doc survives the segment's deletes. Deleted documents are
+/// dropped from the permutation so the serialized segment is compacted as it is
+/// reordered.
+pub(crate) fn compute_doc_id_mapping<A, V>(
+    sort_by_field: &IndexSortByField,
+    max_doc: DocId,
+    alive: A,
+    value_for: V,
+) -> DocIdMapping
+where
+    A: Fn(DocId) -> bool,
+    V: Fn(DocId) -> u64,
+{
+    let mut doc_ids: Vec<DocId> = (0..max_doc).filter(|&doc| alive(doc)).collect();
+    // A stable sort keeps documents with equal sort values in their original
+    // relative order, which keeps merges deterministic. Sorting by
+    // `Reverse(value)` for `Desc` (instead of sorting ascending and then
+    // reversing the whole vector) preserves that tie order instead of
+    // flipping it, matching `k_way_merge`'s tie-break of favoring the lower
+    // `segment_ord`.
+    match sort_by_field.order {
+        Order::Asc => doc_ids.sort_by_key(|&doc| value_for(doc)),
+        Order::Desc => doc_ids.sort_by_key(|&doc| std::cmp::Reverse(value_for(doc))),
+    }
+    DocIdMapping::from_new_to_old(doc_ids, max_doc)
+}
+
+/// Returns the dense `.del` bytes of an `AliveBitSet` in the new doc-id space,
+/// ready for `AliveBitSet::open`.
+///
+/// Because the permutation already drops deleted documents, every doc in the
+/// remapped space is alive.
+pub(crate) fn remap_alive_bitset(mapping: &DocIdMapping) -> Vec<u8> {
+    let num_docs = mapping.num_docs();
+    let num_bytes = ((num_docs as usize) + 7) / 8;
+    // Every retained document is alive in the new space. Set exactly the
+    // `0..num_docs` bits and leave the padding bits of the final byte zero, so
+    // that `AliveBitSet::open`'s `count_ones()` tally matches `num_docs` and
+    // `alive_docs()` never yields a phantom `DocId` past the end (mirrors
+    // `AliveBitSet::serialize`).
+    let mut buffer = vec![0u8; num_bytes];
+    for doc in 0..num_docs {
+        buffer[(doc / 8) as usize] |= 1u8 << (doc % 8);
+    }
+    buffer
+}
+
+/// Source stream for a k-way merge: an old-doc-id iterator already ordered by
+/// the sort field, tagged with the segment ordinal it belongs to.
+pub(crate) struct SortedSegmentDocs<I> {
+    /// Ordinal of the segment these docs come from.
+    pub segment_ord: usize,
+    /// Old `DocId`s of the segment, in the merge's sort-field order
+    /// (ascending for `Asc`, descending for `Desc`).
+    pub doc_ids: I,
+}
+
+/// Performs a k-way merge across the per-segment sorted doc streams, producing
+/// the merged order as `(segment_ord, old_doc_id)` pairs.
+///
+/// `value_for` yields the sort value of an old `DocId` within a given segment;
+/// `order` flips the comparison for a descending sort. This is the merge-time
+/// analogue of [`compute_doc_id_mapping`].
+pub(crate) fn k_way_merge<I, V>(
+    mut streams: Vec<SortedSegmentDocs<I>>,
+    order: Order,
+    value_for: V,
+) -> Vec<(usize, DocId)>
+where
+    I: Iterator<Item = DocId>,
+    V: Fn(usize, DocId) -> u64,
+{
+    // Prime each stream with its head document.
+    let mut heads: Vec<Option<DocId>> = streams
+        .iter_mut()
+        .map(|stream| stream.doc_ids.next())
+        .collect();
+    let mut merged = Vec::new();
+    loop {
+        // `i` indexes `heads`/`streams`; the segment ordinal to compare and
+        // return is `streams[i].segment_ord`, which may not equal `i` itself
+        // -- `streams` can be an arbitrary, non-contiguous subset of segments
+        // (e.g. a partial merge), so the two must not be conflated.
+        let mut best: Option<usize> = None;
+        for (i, head) in heads.iter().enumerate() {
+            if let Some(doc_id) = *head {
+                let is_better = match best {
+                    None => true,
+                    Some(best_i) => {
+                        let candidate = value_for(streams[i].segment_ord, doc_id);
+                        let incumbent =
+                            value_for(streams[best_i].segment_ord, heads[best_i].unwrap());
+                        if candidate == incumbent {
+                            // Ties go to the lower `segment_ord`, whatever the order.
+                            streams[i].segment_ord < streams[best_i].segment_ord
+                        } else if order == Order::Desc {
+                            candidate > incumbent
+                        } else {
+                            candidate < incumbent
+                        }
+                    }
+                };
+                if is_better {
+                    best = Some(i);
+                }
+            }
+        }
+        match best {
+            None => break,
+            Some(i) => {
+                let doc_id = heads[i].unwrap();
+                merged.push((streams[i].segment_ord, doc_id));
+                heads[i] = streams[i].doc_ids.next();
+            }
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // docs 0..4 all share the same sort value; doc 4 is the odd one out.
+    const VALUES: [u64; 5] = [10, 10, 10, 10, 20];
+
+    fn sort_by_field(order: Order) -> IndexSortByField {
+        IndexSortByField {
+            field: "field".to_string(),
+            order,
+        }
+    }
+
+    #[test]
+    fn compute_doc_id_mapping_keeps_source_order_on_ties_ascending() {
+        let mapping = compute_doc_id_mapping(
+            &sort_by_field(Order::Asc),
+            VALUES.len() as DocId,
+            |_doc| true,
+            |doc| VALUES[doc as usize],
+        );
+        let old_ids: Vec<DocId> = mapping.iter_old_doc_ids().collect();
+        assert_eq!(old_ids, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn compute_doc_id_mapping_keeps_source_order_on_ties_descending() {
+        let mapping = compute_doc_id_mapping(
+            &sort_by_field(Order::Desc),
+            VALUES.len() as DocId,
+            |_doc| true,
+            |doc| VALUES[doc as usize],
+        );
+        let old_ids: Vec<DocId> = mapping.iter_old_doc_ids().collect();
+        // The tied docs (0..4) keep their original relative order even though
+        // the odd one out (4, the largest value) sorts first.
+        assert_eq!(old_ids, vec![4, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn k_way_merge_breaks_ties_by_lower_segment_ord_regardless_of_order() {
+        let value_for = |segment_ord: usize, _doc: DocId| -> u64 {
+            // Every segment's head document ties on the sort value.
+            let _ = segment_ord;
+            10
+        };
+        for order in [Order::Asc, Order::Desc] {
+            let streams = vec![
+                SortedSegmentDocs {
+                    segment_ord: 0,
+                    doc_ids: vec![0u32].into_iter(),
+                },
+                SortedSegmentDocs {
+                    segment_ord: 1,
+                    doc_ids: vec![0u32].into_iter(),
+                },
+            ];
+            let merged = k_way_merge(streams, order, value_for);
+            assert_eq!(merged, vec![(0, 0), (1, 0)]);
+        }
+    }
+
+    #[test]
+    fn k_way_merge_reports_the_streams_own_segment_ord_not_its_vec_position() {
+        // A partial merge: the two streams' `segment_ord`s (5 and 2) are
+        // neither contiguous nor in ascending order, and neither matches its
+        // position in `streams`. `value_for` keys off `segment_ord`, so a
+        // mix-up between the two would also pick the wrong head document.
+        let values: std::collections::HashMap<usize, Vec<u64>> =
+            vec![(5, vec![10, 30]), (2, vec![20])].into_iter().collect();
+        let streams = vec![
+            SortedSegmentDocs {
+                segment_ord: 5,
+                doc_ids: vec![0u32, 1u32].into_iter(),
+            },
+            SortedSegmentDocs {
+                segment_ord: 2,
+                doc_ids: vec![0u32].into_iter(),
+            },
+        ];
+        let merged = k_way_merge(streams, Order::Asc, |segment_ord, doc_id| {
+            values[&segment_ord][doc_id as usize]
+        });
+        // segment 5's doc 0 (10) < segment 2's doc 0 (20) < segment 5's doc 1 (30).
+        assert_eq!(merged, vec![(5, 0), (2, 0), (5, 1)]);
+    }
+}
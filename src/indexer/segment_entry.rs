@@ -1,10 +1,10 @@
-use crate::common::BitSet;
+use crate::common::AliveBitSet;
 use crate::core::SegmentDirectory;
 use crate::core::SegmentId;
 use crate::core::SegmentMeta;
 use crate::directory::ManagedDirectory;
 use crate::indexer::delete_queue::DeleteCursor;
-use crate::Directory;
+use crate::indexer::segment_persister::persist_segment_entry;
 use std::fmt;
 
 /// A segment entry describes the state of
@@ -14,16 +14,16 @@ use std::fmt;
 /// it contains a few transient states
 /// - `state` expresses whether the segment is already in the
 /// middle of a merge
-/// - `delete_bitset` is a bitset describing
-/// documents that were deleted during the commit
+/// - `alive_bitset` is an alive-doc bitset: a set bit means the document is
+/// still alive. It reflects the deletes that happened during the commit
 /// itself.
 /// - `delete_cursor` is the position in the delete queue.
 /// Deletes happening before the cursor are reflected either
-/// in the .del file or in the `delete_bitset`.
+/// in the .del file or in the `alive_bitset`.
 #[derive(Clone)]
 pub struct SegmentEntry {
     meta: SegmentMeta,
-    delete_bitset: Option<BitSet>,
+    alive_bitset: Option<AliveBitSet>,
     delete_cursor: DeleteCursor,
     directory: SegmentDirectory,
 }
@@ -33,29 +33,53 @@ impl SegmentEntry {
     pub(crate) fn new(
         segment_meta: SegmentMeta,
         delete_cursor: DeleteCursor,
-        delete_bitset: Option<BitSet>,
+        alive_bitset: Option<AliveBitSet>,
         directory: SegmentDirectory,
     ) -> SegmentEntry {
         SegmentEntry {
             meta: segment_meta,
-            delete_bitset,
+            alive_bitset,
             delete_cursor,
             directory,
         }
     }
 
-    pub fn persist(&mut self, mut directory: ManagedDirectory) -> crate::Result<()> {
-        //if let Some(volatile_directory) = self.volatile_directory.take() {}
-        unimplemented!();
-        self.directory = SegmentDirectory::Persisted(directory);
-        Ok(())
+    /// Persists a volatile (RAM-backed) segment into the given
+    /// `ManagedDirectory`.
+    ///
+    /// The `SegmentComponent` files of the in-memory `RAMDirectory` are
+    /// bundled into a single `.pack` file, with the `.del` region written from
+    /// this entry's alive bitset; that file is fsynced and registered with the
+    /// managed directory's file tracker before `self.directory` is swapped to
+    /// `SegmentDirectory::Persisted`. Once persisted, calling this method is a
+    /// no-op.
+    ///
+    /// Indexing threads usually hand segments off to the
+    /// [`SegmentPersister`](crate::indexer::segment_persister::SegmentPersister)
+    /// pool rather than calling this directly; it is exposed so that a segment
+    /// which is mid-merge can be persisted synchronously before the merge is
+    /// enqueued.
+    pub fn persist(&mut self, directory: ManagedDirectory) -> crate::Result<()> {
+        persist_segment_entry(self, directory)
+    }
+
+    /// Returns the `SegmentDirectory` currently backing this entry.
+    pub(crate) fn directory(&self) -> &SegmentDirectory {
+        &self.directory
+    }
+
+    /// Swaps the directory backing this entry.
+    ///
+    /// This is how persistence flips a segment from `Volatile` to `Persisted`.
+    pub(crate) fn set_directory(&mut self, directory: SegmentDirectory) {
+        self.directory = directory;
     }
 
-    /// Return a reference to the segment entry deleted bitset.
+    /// Return a reference to the segment entry alive bitset.
     ///
-    /// `DocId` in this bitset are flagged as deleted.
-    pub fn delete_bitset(&self) -> Option<&BitSet> {
-        self.delete_bitset.as_ref()
+    /// `DocId` set in this bitset are flagged as alive.
+    pub fn alive_bitset(&self) -> Option<&AliveBitSet> {
+        self.alive_bitset.as_ref()
     }
 
     /// Set the `SegmentMeta` for this segment.